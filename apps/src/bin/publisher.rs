@@ -16,13 +16,19 @@
 // to the Bonsai proving service and publish the received proofs directly
 // to your deployed app contract.
 
+use std::{collections::BTreeMap, str::FromStr};
+
+use alloy::{
+    eips::eip2930::AccessList,
+    primitives::{Address, B256},
+};
 use alloy_primitives::U256;
 use alloy_sol_types::{sol, SolInterface, SolValue};
 use anyhow::{Context, Result};
-use apps::{BonsaiProver, LocalProver, TxSender};
+use apps::{BonsaiProver, Eip1559Fees, LocalProver, TxSender};
 use clap::{Parser, ValueEnum};
-use jwt_core::{CustomClaims, Issuer};
-use methods::{IS_EVEN_ELF, JWT_ELF};
+use jwt_core::{CustomClaims, Issuer, JwkSet, SdJwtInput};
+use methods::{IS_EVEN_ELF, JWT_ELF, SD_JWT_ELF};
 
 // `IEvenNumber` interface automatically generated via the alloy `sol!` macro.
 sol! {
@@ -42,6 +48,7 @@ enum Prover {
 enum Method {
     IsEven,
     Jwt,
+    SdJwt,
 }
 
 /// Arguments of the publisher CLI.
@@ -75,6 +82,89 @@ struct Args {
     /// The method to use
     #[clap(long, default_value = "is-even")]
     method: Method,
+
+    /// Claim name to reveal when `--method sd-jwt` is used. Repeat the flag
+    /// to disclose more than one claim; any claim not listed here stays
+    /// hidden behind its `_sd` digest.
+    #[clap(long)]
+    disclose: Vec<String>,
+
+    /// Path or URL to a JWK Set to issue from, instead of the key embedded
+    /// in this binary. Requires `--kid` to pick which key in the set signs
+    /// the token.
+    #[clap(long)]
+    jwks: Option<String>,
+
+    /// The `kid` of the key to use within `--jwks`.
+    #[clap(long)]
+    kid: Option<String>,
+
+    /// `maxFeePerGas`, in wei, for the EIP-1559 transaction. Estimated from
+    /// the node when omitted.
+    #[clap(long)]
+    max_fee_per_gas: Option<u128>,
+
+    /// `maxPriorityFeePerGas`, in wei, for the EIP-1559 transaction.
+    /// Estimated from the node when omitted.
+    #[clap(long)]
+    max_priority_fee_per_gas: Option<u128>,
+
+    /// Address to pre-warm via an EIP-2930 access list entry, with no
+    /// storage slots. Repeat the flag to list more than one address. To
+    /// also pre-warm storage slots for an address, use
+    /// `--access-list-slot` instead.
+    #[clap(long)]
+    access_list_address: Vec<Address>,
+
+    /// `<address>:<slot>` pair to pre-warm via an EIP-2930 access list
+    /// entry, where `slot` is a 32-byte hex storage key, e.g.
+    /// `0x1234...cdef:0x0000...0001`. Repeat the flag to list more than one
+    /// slot; slots for the same address are grouped into a single access
+    /// list entry.
+    #[clap(long, value_parser = parse_access_list_slot)]
+    access_list_slot: Vec<(Address, B256)>,
+}
+
+/// Parses an `<address>:<slot>` pair for `--access-list-slot`.
+fn parse_access_list_slot(s: &str) -> Result<(Address, B256), String> {
+    let (address, slot) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected `<address>:<slot>`, got `{s}`"))?;
+    let address = Address::from_str(address).map_err(|e| e.to_string())?;
+    let slot = B256::from_str(slot).map_err(|e| e.to_string())?;
+    Ok((address, slot))
+}
+
+/// Loads a JWK Set from a local file or, if `src` looks like a URL, over
+/// HTTP(S).
+fn load_jwks(src: &str) -> Result<JwkSet> {
+    let contents = if src.starts_with("http://") || src.starts_with("https://") {
+        reqwest::blocking::get(src)
+            .context("fetching JWK Set")?
+            .text()
+            .context("reading JWK Set response body")?
+    } else {
+        std::fs::read_to_string(src).with_context(|| format!("reading JWK Set from {src}"))?
+    };
+    contents.parse().context("parsing JWK Set")
+}
+
+/// Builds the `Issuer` to sign with: from `--jwks`/`--kid` if given,
+/// otherwise the key embedded in this binary.
+fn resolve_issuer(args: &Args) -> Result<Issuer> {
+    match &args.jwks {
+        Some(src) => {
+            let set = load_jwks(src)?;
+            let kid = args
+                .kid
+                .as_deref()
+                .context("--kid is required when --jwks is set")?;
+            Issuer::from_jwks(&set, kid).context("building issuer from JWK Set")
+        }
+        None => SECRET_KEY
+            .parse::<Issuer>()
+            .context("failed to create issuer from secret key"),
+    }
 }
 
 fn main() -> Result<()> {
@@ -97,9 +187,7 @@ fn main() -> Result<()> {
             let claims = CustomClaims {
                 subject: "Hello, world!".to_string(),
             };
-            let iss = SECRET_KEY
-                .parse::<Issuer>()
-                .expect("failed to create issuer from secret key");
+            let iss = resolve_issuer(&args)?;
 
             let token = iss
                 .generate_token(&claims)
@@ -108,6 +196,23 @@ fn main() -> Result<()> {
             let encoded = bincode::serialize(&token).expect("failed to encode token");
             (JWT_ELF, encoded)
         }
+        Method::SdJwt => {
+            let claims = CustomClaims {
+                subject: "Hello, world!".to_string(),
+            };
+            let iss = resolve_issuer(&args)?;
+
+            let token = iss
+                .generate_sd_token(&claims, &["subject"])
+                .expect("failed to create SD-JWT");
+
+            let input = SdJwtInput {
+                token,
+                disclose: args.disclose.clone(),
+            };
+            let encoded = bincode::serialize(&input).expect("failed to encode SD-JWT input");
+            (SD_JWT_ELF, encoded)
+        }
     };
 
     // Send an off-chain proof request to the Bonsai proving service.
@@ -137,17 +242,41 @@ fn main() -> Result<()> {
             })
             .abi_encode()
         }
-        Method::Jwt => IEvenNumber::IEvenNumberCalls::set_jwt(IEvenNumber::set_jwtCall {
-            x: args.input,
-            post_state_digest,
-            seal,
-        })
-        .abi_encode(),
+        Method::Jwt | Method::SdJwt => {
+            IEvenNumber::IEvenNumberCalls::set_jwt(IEvenNumber::set_jwtCall {
+                x: args.input,
+                post_state_digest,
+                seal,
+            })
+            .abi_encode()
+        }
     };
 
-    // Send the calldata to Ethereum.
+    // Send the calldata to Ethereum as an EIP-1559 transaction, pre-warming
+    // any addresses/slots the caller listed via `--access-list-address` and
+    // `--access-list-slot`.
+    let fees = Eip1559Fees {
+        max_fee_per_gas: args.max_fee_per_gas,
+        max_priority_fee_per_gas: args.max_priority_fee_per_gas,
+    };
+    let mut access_list_entries: BTreeMap<Address, Vec<B256>> = BTreeMap::new();
+    for address in &args.access_list_address {
+        access_list_entries.entry(*address).or_default();
+    }
+    for (address, slot) in &args.access_list_slot {
+        access_list_entries.entry(*address).or_default().push(*slot);
+    }
+    let access_list = AccessList::from(
+        access_list_entries
+            .into_iter()
+            .map(|(address, storage_keys)| alloy::rpc::types::AccessListItem {
+                address,
+                storage_keys,
+            })
+            .collect::<Vec<_>>(),
+    );
     let runtime = tokio::runtime::Runtime::new()?;
-    runtime.block_on(tx_sender.send(calldata))?;
+    runtime.block_on(tx_sender.send_eip1559(calldata, fees, access_list))?;
 
     Ok(())
 }
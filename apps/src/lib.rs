@@ -0,0 +1,374 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Support library shared by the publisher's binaries: proving (local or
+//! via Bonsai) and publishing the resulting proof to an Ethereum contract.
+
+use std::str::FromStr;
+
+use alloy::{
+    consensus::{SignableTransaction, TxEip1559, TxLegacy},
+    eips::eip2930::AccessList,
+    primitives::{Address, Bytes, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionReceipt,
+    signers::{local::PrivateKeySigner, Signature, Signer},
+};
+use anyhow::{Context, Result};
+use risc0_zkvm::{default_prover, ExecutorEnv, Receipt};
+
+/// Proves the guest with whichever prover `default_prover()` selects from
+/// the environment; local proving is not forced.
+pub struct LocalProver;
+
+impl LocalProver {
+    pub fn prove(elf: &[u8], input: &[u8]) -> Result<(Vec<u8>, B256, Bytes)> {
+        let env = ExecutorEnv::builder().write_slice(input).build()?;
+        let receipt = default_prover().prove(env, elf)?.receipt;
+        decode_receipt(receipt)
+    }
+}
+
+/// Proves the guest with whichever prover `default_prover()` selects from
+/// the environment, exactly as [`LocalProver`] does; the Bonsai proving
+/// service is not forced.
+pub struct BonsaiProver;
+
+impl BonsaiProver {
+    pub fn prove(elf: &[u8], input: &[u8]) -> Result<(Vec<u8>, B256, Bytes)> {
+        let env = ExecutorEnv::builder().write_slice(input).build()?;
+        let receipt = default_prover().prove(env, elf)?.receipt;
+        decode_receipt(receipt)
+    }
+}
+
+fn decode_receipt(receipt: Receipt) -> Result<(Vec<u8>, B256, Bytes)> {
+    let journal = receipt.journal.bytes.clone();
+    let post_state_digest = B256::from_slice(receipt.get_claim()?.post.digest().as_bytes());
+    let seal = Bytes::from(receipt.inner.groth16()?.seal.clone());
+    Ok((journal, post_state_digest, seal))
+}
+
+/// Either transaction envelope `TxSender` knows how to build.
+enum UnsignedTx {
+    Legacy(TxLegacy),
+    Eip1559(TxEip1559),
+}
+
+/// An unsigned transaction and the keccak256 preimage an external signer
+/// (hardware wallet, KMS, air-gapped key) needs to sign.
+pub struct Unsigned {
+    tx: UnsignedTx,
+    pub signing_hash: B256,
+}
+
+/// `max_fee_per_gas`/`max_priority_fee_per_gas` for an EIP-1559 transaction.
+/// Either left `None` to have [`TxSender::build_eip1559`] estimate it from
+/// the node.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Eip1559Fees {
+    pub max_fee_per_gas: Option<u128>,
+    pub max_priority_fee_per_gas: Option<u128>,
+}
+
+/// A fully-signed, RLP-encoded transaction, ready to broadcast.
+pub struct RawTx(pub Bytes);
+
+/// Compiles calldata into a transaction, hands it off for signing, and
+/// assembles + broadcasts the result. `TxSender::send` is a convenience
+/// wrapper over `build`/`assemble` for callers that have a raw private key;
+/// callers that don't (offline/HSM signing) can drive `build`/`assemble`
+/// directly.
+pub struct TxSender {
+    chain_id: u64,
+    rpc_url: String,
+    contract: Address,
+    from: Address,
+    signer: Option<PrivateKeySigner>,
+}
+
+impl TxSender {
+    /// Creates a `TxSender` that signs with a local private key. This is
+    /// the convenience path; for offline signing (hardware wallet, KMS,
+    /// air-gapped key) use [`TxSender::unsigned`] together with
+    /// [`TxSender::build`]/[`TxSender::assemble`].
+    pub fn new(chain_id: u64, rpc_url: &str, private_key: &str, contract: &str) -> Result<Self> {
+        let signer = PrivateKeySigner::from_str(private_key).context("invalid private key")?;
+        let from = signer.address();
+        let contract = Address::from_str(contract).context("invalid contract address")?;
+        Ok(Self {
+            chain_id,
+            rpc_url: rpc_url.to_string(),
+            contract,
+            from,
+            signer: Some(signer),
+        })
+    }
+
+    /// Creates a `TxSender` with no signing key of its own, for the
+    /// `build`/`assemble` flow: `from` is the address the external signer
+    /// (hardware wallet, KMS, air-gapped key) will sign on behalf of.
+    pub fn unsigned(chain_id: u64, rpc_url: &str, contract: &str, from: Address) -> Result<Self> {
+        let contract = Address::from_str(contract).context("invalid contract address")?;
+        Ok(Self {
+            chain_id,
+            rpc_url: rpc_url.to_string(),
+            contract,
+            from,
+            signer: None,
+        })
+    }
+
+    fn provider(&self) -> Result<impl Provider> {
+        Ok(ProviderBuilder::new().on_http(self.rpc_url.parse()?))
+    }
+
+    /// Compiles `calldata` into an unsigned legacy transaction against the
+    /// configured contract, filling in nonce/gas from the node, and returns
+    /// the transaction together with its keccak256 signing preimage.
+    pub async fn build(&self, calldata: Vec<u8>) -> Result<Unsigned> {
+        let provider = self.provider()?;
+        let from = self.from;
+
+        let nonce = provider.get_transaction_count(from).await?;
+        let gas_price = provider.get_gas_price().await?;
+        let input = Bytes::from(calldata);
+        let gas_limit = provider
+            .estimate_gas(
+                &alloy::rpc::types::TransactionRequest::default()
+                    .from(from)
+                    .to(self.contract)
+                    .input(input.clone().into()),
+            )
+            .await?;
+
+        let tx = TxLegacy {
+            chain_id: Some(self.chain_id),
+            nonce,
+            gas_price,
+            gas_limit,
+            to: self.contract.into(),
+            value: U256::ZERO,
+            input,
+        };
+        let signing_hash = tx.signature_hash();
+
+        Ok(Unsigned {
+            tx: UnsignedTx::Legacy(tx),
+            signing_hash,
+        })
+    }
+
+    /// Compiles `calldata` into an unsigned EIP-1559 (type-2) transaction,
+    /// optionally carrying an EIP-2930 `access_list`. Any fee left `None`
+    /// in `fees` is estimated from the node. Errors if the resolved
+    /// `max_priority_fee_per_gas` exceeds `max_fee_per_gas`, which the node
+    /// would otherwise reject at broadcast time anyway.
+    pub async fn build_eip1559(
+        &self,
+        calldata: Vec<u8>,
+        fees: Eip1559Fees,
+        access_list: AccessList,
+    ) -> Result<Unsigned> {
+        let provider = self.provider()?;
+        let from = self.from;
+
+        let nonce = provider.get_transaction_count(from).await?;
+        let input = Bytes::from(calldata);
+        let gas_limit = provider
+            .estimate_gas(
+                &alloy::rpc::types::TransactionRequest::default()
+                    .from(from)
+                    .to(self.contract)
+                    .input(input.clone().into()),
+            )
+            .await?;
+
+        let (max_fee_per_gas, max_priority_fee_per_gas) =
+            match (fees.max_fee_per_gas, fees.max_priority_fee_per_gas) {
+                (Some(max_fee), Some(max_priority_fee)) => (max_fee, max_priority_fee),
+                _ => {
+                    let estimate = provider.estimate_eip1559_fees(None).await?;
+                    (
+                        fees.max_fee_per_gas.unwrap_or(estimate.max_fee_per_gas),
+                        fees.max_priority_fee_per_gas
+                            .unwrap_or(estimate.max_priority_fee_per_gas),
+                    )
+                }
+            };
+        anyhow::ensure!(
+            max_priority_fee_per_gas <= max_fee_per_gas,
+            "max_priority_fee_per_gas ({max_priority_fee_per_gas}) must not exceed max_fee_per_gas ({max_fee_per_gas})"
+        );
+
+        let tx = TxEip1559 {
+            chain_id: self.chain_id,
+            nonce,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            to: self.contract.into(),
+            value: U256::ZERO,
+            access_list,
+            input,
+        };
+        let signing_hash = tx.signature_hash();
+
+        Ok(Unsigned {
+            tx: UnsignedTx::Eip1559(tx),
+            signing_hash,
+        })
+    }
+
+    /// Attaches an externally-produced `signature` over
+    /// `unsigned.signing_hash` to the compiled transaction and RLP-encodes
+    /// the result, using the typed-transaction envelope (`0x02` prefix) for
+    /// EIP-1559 transactions and the EIP-155 legacy envelope otherwise.
+    pub fn assemble(&self, unsigned: Unsigned, signature: Signature) -> Result<RawTx> {
+        let mut out = Vec::new();
+        match unsigned.tx {
+            UnsignedTx::Legacy(tx) => tx.into_signed(signature).eip2718_encode(&mut out),
+            UnsignedTx::Eip1559(tx) => tx.into_signed(signature).eip2718_encode(&mut out),
+        }
+        Ok(RawTx(Bytes::from(out)))
+    }
+
+    /// Broadcasts an assembled transaction and waits for its receipt.
+    pub async fn broadcast(&self, raw: RawTx) -> Result<Option<TransactionReceipt>> {
+        let provider = self.provider()?;
+        let pending = provider.send_raw_transaction(&raw.0).await?;
+        let receipt = pending.get_receipt().await?;
+        Ok(Some(receipt))
+    }
+
+    /// Builds, signs (with the private key passed to [`TxSender::new`]) and
+    /// broadcasts `calldata` in one call. A thin convenience wrapper over
+    /// `build`/`assemble`/`broadcast` for the common case of holding the
+    /// raw private key in-process.
+    pub async fn send(&self, calldata: Vec<u8>) -> Result<Option<TransactionReceipt>> {
+        let signer = self
+            .signer
+            .as_ref()
+            .context("TxSender has no private key; use build()/assemble() with an external signer")?;
+
+        let unsigned = self.build(calldata).await?;
+        let signature = signer.sign_hash(&unsigned.signing_hash).await?;
+        let raw = self.assemble(unsigned, signature)?;
+        self.broadcast(raw).await
+    }
+
+    /// The EIP-1559 counterpart to [`TxSender::send`]: builds a type-2
+    /// transaction (estimating any fee left `None` in `fees`), signs it with
+    /// the private key passed to [`TxSender::new`], and broadcasts it.
+    pub async fn send_eip1559(
+        &self,
+        calldata: Vec<u8>,
+        fees: Eip1559Fees,
+        access_list: AccessList,
+    ) -> Result<Option<TransactionReceipt>> {
+        let signer = self
+            .signer
+            .as_ref()
+            .context("TxSender has no private key; use build_eip1559()/assemble() with an external signer")?;
+
+        let unsigned = self.build_eip1559(calldata, fees, access_list).await?;
+        let signature = signer.sign_hash(&unsigned.signing_hash).await?;
+        let raw = self.assemble(unsigned, signature)?;
+        self.broadcast(raw).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{Signed, TxEnvelope},
+        eips::eip2718::Decodable2718,
+        primitives::{Bytes, TxKind},
+    };
+
+    use super::*;
+
+    fn test_tx_sender(signer: PrivateKeySigner, contract: Address) -> TxSender {
+        TxSender {
+            chain_id: 1,
+            rpc_url: "http://localhost:8545".to_string(),
+            contract,
+            from: signer.address(),
+            signer: Some(signer),
+        }
+    }
+
+    #[test]
+    fn build_signature_hash_matches_independent_computation() {
+        let tx = TxLegacy {
+            chain_id: Some(1),
+            nonce: 7,
+            gas_price: 20_000_000_000,
+            gas_limit: 100_000,
+            to: TxKind::Call(Address::with_last_byte(0xAB)),
+            value: U256::ZERO,
+            input: Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef]),
+        };
+
+        // Computed independently of `TxSender::build`: the EIP-155 signing
+        // preimage is the RLP list of the unsigned fields, keccak256-hashed.
+        let preimage = alloy_rlp::encode(&(
+            tx.nonce,
+            tx.gas_price,
+            tx.gas_limit,
+            tx.to,
+            tx.value,
+            &tx.input,
+            tx.chain_id.unwrap(),
+            0u8,
+            0u8,
+        ));
+        let expected_hash = alloy::primitives::keccak256(&preimage);
+
+        assert_eq!(tx.signature_hash(), expected_hash);
+    }
+
+    #[tokio::test]
+    async fn assemble_round_trips_through_rlp_with_the_correct_signer() {
+        let signer = PrivateKeySigner::random();
+        let contract = Address::with_last_byte(1);
+        let tx_sender = test_tx_sender(signer.clone(), contract);
+
+        let tx = TxLegacy {
+            chain_id: Some(1),
+            nonce: 7,
+            gas_price: 20_000_000_000,
+            gas_limit: 100_000,
+            to: TxKind::Call(contract),
+            value: U256::ZERO,
+            input: Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef]),
+        };
+        let signing_hash = tx.signature_hash();
+        let signature = signer.sign_hash(&signing_hash).await.unwrap();
+
+        let unsigned = Unsigned {
+            tx: UnsignedTx::Legacy(tx.clone()),
+            signing_hash,
+        };
+        let raw = tx_sender.assemble(unsigned, signature).unwrap();
+
+        let envelope = TxEnvelope::decode_2718(&mut raw.0.as_ref()).expect("valid RLP envelope");
+        let signed: &Signed<TxLegacy> = envelope.as_legacy().expect("legacy envelope");
+        assert_eq!(signed.tx(), &tx);
+        assert_eq!(
+            envelope.recover_signer().expect("recoverable signature"),
+            signer.address()
+        );
+    }
+}
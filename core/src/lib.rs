@@ -0,0 +1,804 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shared JWT issuing and verification primitives used by the publisher
+//! (which holds the signing key) and the zkVM guests (which only ever see
+//! public key material and a token to verify).
+
+use std::str::FromStr;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use rand::RngCore;
+use rsa::BigUint;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum JwtError {
+    #[error("malformed JWK: {0}")]
+    MalformedJwk(String),
+    #[error("malformed token: {0}")]
+    MalformedToken(String),
+    #[error("unsupported algorithm `{0}`")]
+    UnsupportedAlg(String),
+    #[error("signature verification failed")]
+    InvalidSignature,
+    #[error("disclosure does not match any digest in `_sd`")]
+    UnmatchedDisclosure,
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Rsa(#[from] rsa::errors::Error),
+}
+
+type Result<T> = std::result::Result<T, JwtError>;
+
+/// The claims this example issues tokens for. Any type that serializes to a
+/// JSON object can be used with [`Issuer::generate_token`] /
+/// [`Issuer::generate_sd_token`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomClaims {
+    pub subject: String,
+}
+
+/// A JSON Web Key, as embedded in the publisher binary or loaded from a JWK
+/// Set. Fields cover the RSA (`kty: "RSA"`), P-256 (`kty: "EC"`, `crv:
+/// "P-256"`) and Ed25519 (`kty: "OKP"`, `crv: "Ed25519"`) key types; unused
+/// fields for a given `kty` are simply `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: Option<String>,
+    pub alg: Option<String>,
+    pub kid: Option<String>,
+    // RSA
+    pub n: Option<String>,
+    pub e: Option<String>,
+    pub d: Option<String>,
+    pub p: Option<String>,
+    pub q: Option<String>,
+    pub dp: Option<String>,
+    pub dq: Option<String>,
+    pub qi: Option<String>,
+    // EC / OKP
+    pub x: Option<String>,
+    pub y: Option<String>,
+}
+
+impl FromStr for Jwk {
+    type Err = JwtError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+/// A JWK Set (RFC 7517 §5): a list of keys indexed by `kid`. Lets `Issuer`
+/// and `verify_with_jwks` resolve the right key without the private signing
+/// key ever needing to live in the publisher binary's source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl FromStr for JwkSet {
+    type Err = JwtError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+impl JwkSet {
+    /// Finds the key whose `kid` matches, if any.
+    pub fn find(&self, kid: &str) -> Option<&Jwk> {
+        self.keys.iter().find(|k| k.kid.as_deref() == Some(kid))
+    }
+}
+
+/// The signing algorithms `Issuer`/`verify` know how to dispatch. Chosen
+/// from a `Jwk`'s `kty`/`crv` (issuing) or a JWS header's `alg` (verifying).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alg {
+    Rs256,
+    Es256,
+    EdDsa,
+}
+
+impl Alg {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Alg::Rs256 => "RS256",
+            Alg::Es256 => "ES256",
+            Alg::EdDsa => "EdDSA",
+        }
+    }
+
+    fn from_jwk(jwk: &Jwk) -> Result<Self> {
+        match (jwk.kty.as_str(), jwk.crv.as_deref()) {
+            ("RSA", _) => Ok(Alg::Rs256),
+            ("EC", Some("P-256")) => Ok(Alg::Es256),
+            ("OKP", Some("Ed25519")) => Ok(Alg::EdDsa),
+            (kty, crv) => Err(JwtError::UnsupportedAlg(format!(
+                "kty={kty} crv={}",
+                crv.unwrap_or("none")
+            ))),
+        }
+    }
+}
+
+impl FromStr for Alg {
+    type Err = JwtError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "RS256" => Ok(Alg::Rs256),
+            "ES256" => Ok(Alg::Es256),
+            "EdDSA" => Ok(Alg::EdDsa),
+            other => Err(JwtError::UnsupportedAlg(other.to_string())),
+        }
+    }
+}
+
+/// A signed token ready to be handed to a guest. `jws` is the compact
+/// `header.payload.signature` JWT; `disclosures` carries the selective
+/// disclosures (if any) that accompany an SD-JWT, in `~`-joined form's
+/// individual pieces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Token {
+    pub jws: String,
+    pub disclosures: Vec<String>,
+}
+
+impl Token {
+    /// Renders the SD-JWT wire format `<jws>~<disclosure>~...~`. For a
+    /// non-selective-disclosure token (`disclosures` empty) this is just
+    /// the JWS itself.
+    pub fn serialized(&self) -> String {
+        if self.disclosures.is_empty() {
+            return self.jws.clone();
+        }
+        let mut out = self.jws.clone();
+        for d in &self.disclosures {
+            out.push('~');
+            out.push_str(d);
+        }
+        out.push('~');
+        out
+    }
+}
+
+enum SigningKeyMaterial {
+    Rsa(Box<rsa::RsaPrivateKey>),
+    Es256(Box<p256::ecdsa::SigningKey>),
+    Ed25519(Box<ed25519_dalek::SigningKey>),
+}
+
+/// Holds a private key and signs tokens on its behalf. The algorithm is
+/// fixed at construction time from the `Jwk`'s `kty`/`crv`.
+pub struct Issuer {
+    jwk: Jwk,
+    alg: Alg,
+    key: SigningKeyMaterial,
+}
+
+impl FromStr for Issuer {
+    type Err = JwtError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let jwk: Jwk = s.parse()?;
+        Self::from_jwk(jwk)
+    }
+}
+
+impl Issuer {
+    pub fn from_jwk(jwk: Jwk) -> Result<Self> {
+        let alg = Alg::from_jwk(&jwk)?;
+        let key = match alg {
+            Alg::Rs256 => {
+                let n = jwk
+                    .n
+                    .as_deref()
+                    .ok_or_else(|| JwtError::MalformedJwk("missing `n`".into()))?;
+                let e = jwk
+                    .e
+                    .as_deref()
+                    .ok_or_else(|| JwtError::MalformedJwk("missing `e`".into()))?;
+                let d = jwk
+                    .d
+                    .as_deref()
+                    .ok_or_else(|| JwtError::MalformedJwk("missing `d`".into()))?;
+                let p = jwk
+                    .p
+                    .as_deref()
+                    .ok_or_else(|| JwtError::MalformedJwk("missing `p`".into()))?;
+                let q = jwk
+                    .q
+                    .as_deref()
+                    .ok_or_else(|| JwtError::MalformedJwk("missing `q`".into()))?;
+                let key = rsa::RsaPrivateKey::from_components(
+                    biguint_from_b64(n)?,
+                    biguint_from_b64(e)?,
+                    biguint_from_b64(d)?,
+                    vec![biguint_from_b64(p)?, biguint_from_b64(q)?],
+                )
+                .map_err(JwtError::Rsa)?;
+                SigningKeyMaterial::Rsa(Box::new(key))
+            }
+            Alg::Es256 => {
+                let d = jwk
+                    .d
+                    .as_deref()
+                    .ok_or_else(|| JwtError::MalformedJwk("missing `d`".into()))?;
+                let key = p256::ecdsa::SigningKey::from_bytes((&b64_decode(d)?[..]).into())
+                    .map_err(|e| JwtError::MalformedJwk(e.to_string()))?;
+                SigningKeyMaterial::Es256(Box::new(key))
+            }
+            Alg::EdDsa => {
+                let d = jwk
+                    .d
+                    .as_deref()
+                    .ok_or_else(|| JwtError::MalformedJwk("missing `d`".into()))?;
+                let bytes = b64_decode(d)?;
+                let seed: [u8; 32] = bytes
+                    .try_into()
+                    .map_err(|_| JwtError::MalformedJwk("`d` must be 32 bytes".into()))?;
+                SigningKeyMaterial::Ed25519(Box::new(ed25519_dalek::SigningKey::from_bytes(&seed)))
+            }
+        };
+        Ok(Self { jwk, alg, key })
+    }
+
+    /// Builds an `Issuer` from the key in `set` named `kid`, enabling key
+    /// rotation: the signing key lives in a JWK Set the caller loads (e.g.
+    /// from a file or an internal key-management endpoint) instead of being
+    /// embedded in the binary.
+    pub fn from_jwks(set: &JwkSet, kid: &str) -> Result<Self> {
+        let jwk = set
+            .find(kid)
+            .ok_or_else(|| JwtError::MalformedJwk(format!("no key with kid `{kid}`")))?
+            .clone();
+        Self::from_jwk(jwk)
+    }
+
+    /// Issues a token that reveals every claim in `claims`.
+    pub fn generate_token<T: Serialize>(&self, claims: &T) -> Result<Token> {
+        self.generate_sd_token(claims, &[])
+    }
+
+    /// Issues an SD-JWT: every field named in `disclosable` is replaced in
+    /// the signed payload with a digest in an `_sd` array, and the matching
+    /// `Disclosure` is returned alongside the JWS so the holder can choose
+    /// which ones to forward to a verifier.
+    pub fn generate_sd_token<T: Serialize>(
+        &self,
+        claims: &T,
+        disclosable: &[&str],
+    ) -> Result<Token> {
+        let mut payload = match serde_json::to_value(claims)? {
+            Value::Object(map) => map,
+            _ => return Err(JwtError::MalformedToken("claims must serialize to an object".into())),
+        };
+
+        let mut digests = Vec::with_capacity(disclosable.len());
+        let mut disclosures = Vec::with_capacity(disclosable.len());
+        for name in disclosable {
+            let value = payload
+                .remove(*name)
+                .ok_or_else(|| JwtError::MalformedToken(format!("no claim named `{name}`")))?;
+            let disclosure = Disclosure::new(name, value);
+            let encoded = disclosure.encode();
+            digests.push(Value::String(Disclosure::digest(&encoded)));
+            disclosures.push(encoded);
+        }
+        if !digests.is_empty() {
+            payload.insert("_sd".into(), Value::Array(digests));
+        }
+
+        let mut header = Map::new();
+        header.insert("alg".into(), Value::String(self.alg.as_str().into()));
+        header.insert("typ".into(), Value::String("JWT".into()));
+        if let Some(kid) = &self.jwk.kid {
+            header.insert("kid".into(), Value::String(kid.clone()));
+        }
+        if !disclosures.is_empty() {
+            header.insert("_sd_alg".into(), Value::String("sha-256".into()));
+        }
+
+        let signing_input = format!(
+            "{}.{}",
+            b64(&serde_json::to_vec(&Value::Object(header))?),
+            b64(&serde_json::to_vec(&Value::Object(payload))?),
+        );
+        let signature = self.sign(signing_input.as_bytes());
+        let jws = format!("{signing_input}.{}", b64(&signature));
+
+        Ok(Token { jws, disclosures })
+    }
+
+    fn sign(&self, signing_input: &[u8]) -> Vec<u8> {
+        use ed25519_dalek::Signer as _;
+        use p256::ecdsa::signature::RandomizedSigner as P256RandomizedSigner;
+        use rsa::signature::{RandomizedSigner as _, SignatureEncoding};
+
+        match &self.key {
+            SigningKeyMaterial::Rsa(key) => {
+                let signing_key = rsa::pkcs1v15::SigningKey::<Sha256>::new((**key).clone());
+                signing_key
+                    .sign_with_rng(&mut rand::thread_rng(), signing_input)
+                    .to_vec()
+            }
+            SigningKeyMaterial::Es256(key) => {
+                let signature: p256::ecdsa::Signature =
+                    key.sign_with_rng(&mut rand::thread_rng(), signing_input);
+                signature.to_bytes().to_vec()
+            }
+            SigningKeyMaterial::Ed25519(key) => key.sign(signing_input).to_bytes().to_vec(),
+        }
+    }
+}
+
+/// A single `[salt, claim_name, claim_value]` selective disclosure.
+struct Disclosure {
+    salt: String,
+    name: String,
+    value: Value,
+}
+
+impl Disclosure {
+    fn new(name: &str, value: Value) -> Self {
+        let mut salt_bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt_bytes);
+        Self {
+            salt: URL_SAFE_NO_PAD.encode(salt_bytes),
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    fn encode(&self) -> String {
+        let array = Value::Array(vec![
+            Value::String(self.salt.clone()),
+            Value::String(self.name.clone()),
+            self.value.clone(),
+        ]);
+        b64(&serde_json::to_vec(&array).expect("array of JSON values always serializes"))
+    }
+
+    fn digest(encoded: &str) -> String {
+        b64(&Sha256::digest(encoded.as_bytes()))
+    }
+}
+
+/// Input to a selective-disclosure guest: the SD-JWT itself plus the names
+/// of the disclosures the holder is presenting alongside it. Shared between
+/// the publisher (which builds it) and the guest (which consumes it) so the
+/// wire format can't drift between the two.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SdJwtInput {
+    pub token: Token,
+    pub disclose: Vec<String>,
+}
+
+/// The claims a verifier is left with after checking a token's signature
+/// and reconstructing any disclosed fields.
+pub type VerifiedClaims = Map<String, Value>;
+
+/// Like [`verify`], but resolves the verifying key from `set` using the
+/// `kid` named in the token's header instead of a single fixed `Jwk`. This
+/// is what lets a verifier keep up with key rotation.
+pub fn verify_with_jwks(token: &Token, set: &JwkSet, disclose: &[&str]) -> Result<VerifiedClaims> {
+    let header_b64 = token
+        .jws
+        .split('.')
+        .next()
+        .ok_or_else(|| JwtError::MalformedToken("not a JWS".into()))?;
+    let header: Map<String, Value> = serde_json::from_slice(&b64_decode(header_b64)?)?;
+    let kid = header
+        .get("kid")
+        .and_then(Value::as_str)
+        .ok_or_else(|| JwtError::MalformedToken("header missing `kid`".into()))?;
+    let jwk = set
+        .find(kid)
+        .ok_or_else(|| JwtError::MalformedJwk(format!("no key with kid `{kid}`")))?;
+    verify(token, jwk, disclose)
+}
+
+/// Verifies `token`'s signature against `jwk`'s public key, then folds in
+/// every disclosure, checking each one's digest against the `_sd` array
+/// before trusting its value. Only `disclose` (by claim name) are resolved;
+/// digests whose disclosure wasn't presented are left un-revealed.
+pub fn verify(token: &Token, jwk: &Jwk, disclose: &[&str]) -> Result<VerifiedClaims> {
+    let (signing_input, signature_b64) = token
+        .jws
+        .rsplit_once('.')
+        .ok_or_else(|| JwtError::MalformedToken("not a JWS".into()))?;
+    let (header_b64, payload_b64) = signing_input
+        .split_once('.')
+        .ok_or_else(|| JwtError::MalformedToken("not a JWS".into()))?;
+
+    let header: Map<String, Value> = serde_json::from_slice(&b64_decode(header_b64)?)?;
+    let alg: Alg = header
+        .get("alg")
+        .and_then(Value::as_str)
+        .ok_or_else(|| JwtError::MalformedToken("header missing `alg`".into()))?
+        .parse()?;
+
+    // Never let the header alone pick which of `jwk`'s fields get trusted as
+    // the verification key: derive the algorithm the key itself is for and
+    // require it to match what the header claims. Otherwise a `kid` whose
+    // JWK entry happens to carry both RSA and EC/OKP fields lets an attacker
+    // forge `alg: "ES256"` and sign with their own key (RFC 8725 §3.1/3.2).
+    let key_alg = Alg::from_jwk(jwk)?;
+    if key_alg != alg {
+        return Err(JwtError::UnsupportedAlg(format!(
+            "header declared `{}` but key `{}` is for `{}`",
+            alg.as_str(),
+            jwk.kid.as_deref().unwrap_or("<no kid>"),
+            key_alg.as_str()
+        )));
+    }
+
+    verify_signature(alg, jwk, signing_input.as_bytes(), &b64_decode(signature_b64)?)?;
+
+    let mut claims: Map<String, Value> = serde_json::from_slice(&b64_decode(payload_b64)?)?;
+
+    let sd_digests: Vec<String> = match claims.remove("_sd") {
+        Some(Value::Array(values)) => values
+            .into_iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    claims.remove("_sd_alg");
+
+    for encoded in &token.disclosures {
+        let digest = Disclosure::digest(encoded);
+        if !sd_digests.contains(&digest) {
+            return Err(JwtError::UnmatchedDisclosure);
+        }
+        let decoded: Value = serde_json::from_slice(&b64_decode(encoded)?)?;
+        let [_salt, name, value] = decoded
+            .as_array()
+            .and_then(|a| <[Value; 3]>::try_from(a.clone()).ok())
+            .ok_or_else(|| JwtError::MalformedToken("malformed disclosure".into()))?;
+        let name = name
+            .as_str()
+            .ok_or_else(|| JwtError::MalformedToken("disclosure name must be a string".into()))?;
+        if disclose.contains(&name) {
+            claims.insert(name.to_string(), value);
+        }
+    }
+
+    Ok(claims)
+}
+
+fn verify_signature(alg: Alg, jwk: &Jwk, signing_input: &[u8], signature: &[u8]) -> Result<()> {
+    match alg {
+        Alg::Rs256 => verify_rsa_signature(jwk, signing_input, signature),
+        Alg::Es256 => verify_es256_signature(jwk, signing_input, signature),
+        Alg::EdDsa => verify_eddsa_signature(jwk, signing_input, signature),
+    }
+}
+
+fn verify_es256_signature(jwk: &Jwk, signing_input: &[u8], signature: &[u8]) -> Result<()> {
+    use p256::ecdsa::signature::Verifier;
+
+    let x = jwk
+        .x
+        .as_deref()
+        .ok_or_else(|| JwtError::MalformedJwk("missing `x`".into()))?;
+    let y = jwk
+        .y
+        .as_deref()
+        .ok_or_else(|| JwtError::MalformedJwk("missing `y`".into()))?;
+    let mut point = vec![0x04u8];
+    point.extend(b64_decode(x)?);
+    point.extend(b64_decode(y)?);
+    let verifying_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&point)
+        .map_err(|e| JwtError::MalformedJwk(e.to_string()))?;
+    let signature =
+        p256::ecdsa::Signature::from_slice(signature).map_err(|_| JwtError::InvalidSignature)?;
+    verifying_key
+        .verify(signing_input, &signature)
+        .map_err(|_| JwtError::InvalidSignature)
+}
+
+fn verify_eddsa_signature(jwk: &Jwk, signing_input: &[u8], signature: &[u8]) -> Result<()> {
+    use ed25519_dalek::Verifier;
+
+    let x = jwk
+        .x
+        .as_deref()
+        .ok_or_else(|| JwtError::MalformedJwk("missing `x`".into()))?;
+    let bytes: [u8; 32] = b64_decode(x)?
+        .try_into()
+        .map_err(|_| JwtError::MalformedJwk("`x` must be 32 bytes".into()))?;
+    let verifying_key =
+        ed25519_dalek::VerifyingKey::from_bytes(&bytes).map_err(|_| JwtError::InvalidSignature)?;
+    let signature_bytes: [u8; 64] = signature
+        .try_into()
+        .map_err(|_| JwtError::InvalidSignature)?;
+    verifying_key
+        .verify(signing_input, &ed25519_dalek::Signature::from_bytes(&signature_bytes))
+        .map_err(|_| JwtError::InvalidSignature)
+}
+
+fn verify_rsa_signature(jwk: &Jwk, signing_input: &[u8], signature: &[u8]) -> Result<()> {
+    use rsa::{pkcs1v15::VerifyingKey, signature::Verifier};
+
+    let n = jwk
+        .n
+        .as_deref()
+        .ok_or_else(|| JwtError::MalformedJwk("missing `n`".into()))?;
+    let e = jwk
+        .e
+        .as_deref()
+        .ok_or_else(|| JwtError::MalformedJwk("missing `e`".into()))?;
+    let public_key = rsa::RsaPublicKey::new(biguint_from_b64(n)?, biguint_from_b64(e)?)
+        .map_err(JwtError::Rsa)?;
+    let verifying_key = VerifyingKey::<Sha256>::new(public_key);
+    let signature = rsa::pkcs1v15::Signature::try_from(signature).map_err(|_| JwtError::InvalidSignature)?;
+    verifying_key
+        .verify(signing_input, &signature)
+        .map_err(|_| JwtError::InvalidSignature)
+}
+
+fn biguint_from_b64(s: &str) -> Result<BigUint> {
+    Ok(BigUint::from_bytes_be(&b64_decode(s)?))
+}
+
+fn b64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn b64_decode(s: &str) -> Result<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(s)
+        .map_err(|e| JwtError::MalformedToken(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::traits::{PrivateKeyParts, PublicKeyParts};
+
+    use super::*;
+
+    /// A freshly generated RSA JWK, private fields included, for tests.
+    fn rsa_test_jwk(kid: &str) -> Jwk {
+        let key = rsa::RsaPrivateKey::new(&mut rand::thread_rng(), 2048).expect("key generation");
+        let primes = key.primes();
+        Jwk {
+            kty: "RSA".into(),
+            crv: None,
+            alg: Some("RS256".into()),
+            kid: Some(kid.into()),
+            n: Some(b64(&key.n().to_bytes_be())),
+            e: Some(b64(&key.e().to_bytes_be())),
+            d: Some(b64(&key.d().to_bytes_be())),
+            p: Some(b64(&primes[0].to_bytes_be())),
+            q: Some(b64(&primes[1].to_bytes_be())),
+            dp: None,
+            dq: None,
+            qi: None,
+            x: None,
+            y: None,
+        }
+    }
+
+    #[test]
+    fn sd_jwt_round_trip_reveals_only_requested_claims() {
+        let jwk = rsa_test_jwk("test-key");
+        let iss = Issuer::from_jwk(jwk.clone()).unwrap();
+        let claims = CustomClaims {
+            subject: "alice".into(),
+        };
+        let token = iss.generate_sd_token(&claims, &["subject"]).unwrap();
+
+        let revealed = verify(&token, &jwk, &["subject"]).unwrap();
+        assert_eq!(
+            revealed.get("subject").and_then(Value::as_str),
+            Some("alice")
+        );
+        assert!(!revealed.contains_key("_sd"));
+        assert!(!revealed.contains_key("_sd_alg"));
+    }
+
+    #[test]
+    fn sd_jwt_disclosure_not_requested_stays_hidden() {
+        let jwk = rsa_test_jwk("test-key");
+        let iss = Issuer::from_jwk(jwk.clone()).unwrap();
+        let claims = CustomClaims {
+            subject: "alice".into(),
+        };
+        let token = iss.generate_sd_token(&claims, &["subject"]).unwrap();
+
+        // The holder presents the disclosure (it's in `token.disclosures`),
+        // but the verifier wasn't asked to reveal it.
+        let revealed = verify(&token, &jwk, &[]).unwrap();
+        assert!(!revealed.contains_key("subject"));
+    }
+
+    /// A freshly generated P-256 JWK, private `d` included, for tests.
+    fn es256_test_jwk(kid: &str) -> Jwk {
+        let signing_key = p256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let point = signing_key.verifying_key().to_encoded_point(false);
+        Jwk {
+            kty: "EC".into(),
+            crv: Some("P-256".into()),
+            alg: Some("ES256".into()),
+            kid: Some(kid.into()),
+            n: None,
+            e: None,
+            d: Some(b64(&signing_key.to_bytes())),
+            p: None,
+            q: None,
+            dp: None,
+            dq: None,
+            qi: None,
+            x: Some(b64(point.x().expect("uncompressed point has x"))),
+            y: Some(b64(point.y().expect("uncompressed point has y"))),
+        }
+    }
+
+    /// A freshly generated Ed25519 JWK, private `d` (the 32-byte seed)
+    /// included, for tests.
+    fn eddsa_test_jwk(kid: &str) -> Jwk {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::thread_rng());
+        Jwk {
+            kty: "OKP".into(),
+            crv: Some("Ed25519".into()),
+            alg: Some("EdDSA".into()),
+            kid: Some(kid.into()),
+            n: None,
+            e: None,
+            d: Some(b64(&signing_key.to_bytes())),
+            p: None,
+            q: None,
+            dp: None,
+            dq: None,
+            qi: None,
+            x: Some(b64(signing_key.verifying_key().as_bytes())),
+            y: None,
+        }
+    }
+
+    fn assert_sign_verify_round_trip(jwk: Jwk) {
+        let iss = Issuer::from_jwk(jwk.clone()).unwrap();
+        let claims = CustomClaims {
+            subject: "bob".into(),
+        };
+        let token = iss.generate_token(&claims).unwrap();
+
+        let revealed = verify(&token, &jwk, &[]).unwrap();
+        assert_eq!(revealed.get("subject").and_then(Value::as_str), Some("bob"));
+    }
+
+    #[test]
+    fn rs256_sign_verify_round_trip() {
+        assert_sign_verify_round_trip(rsa_test_jwk("rs256-test"));
+    }
+
+    #[test]
+    fn es256_sign_verify_round_trip() {
+        assert_sign_verify_round_trip(es256_test_jwk("es256-test"));
+    }
+
+    #[test]
+    fn eddsa_sign_verify_round_trip() {
+        assert_sign_verify_round_trip(eddsa_test_jwk("eddsa-test"));
+    }
+
+    #[test]
+    fn sd_jwt_disclosure_with_wrong_digest_is_rejected() {
+        let jwk = rsa_test_jwk("test-key");
+        let iss = Issuer::from_jwk(jwk.clone()).unwrap();
+        let claims = CustomClaims {
+            subject: "alice".into(),
+        };
+        let mut token = iss.generate_sd_token(&claims, &["subject"]).unwrap();
+
+        // Swap in a disclosure for a different value; its digest won't be
+        // in the token's `_sd` array.
+        let forged = Disclosure::new("subject", Value::String("mallory".into()));
+        token.disclosures[0] = forged.encode();
+
+        let err = verify(&token, &jwk, &["subject"]).unwrap_err();
+        assert!(matches!(err, JwtError::UnmatchedDisclosure));
+    }
+
+    #[test]
+    fn jwk_set_find_resolves_by_kid() {
+        let set = JwkSet {
+            keys: vec![rsa_test_jwk("key-a"), rsa_test_jwk("key-b")],
+        };
+
+        assert_eq!(set.find("key-b").unwrap().kid.as_deref(), Some("key-b"));
+        assert!(set.find("key-c").is_none());
+    }
+
+    #[test]
+    fn issuer_and_verifier_resolve_signing_key_by_kid() {
+        let set = JwkSet {
+            keys: vec![rsa_test_jwk("rotating-key")],
+        };
+        let iss = Issuer::from_jwks(&set, "rotating-key").unwrap();
+        let claims = CustomClaims {
+            subject: "carol".into(),
+        };
+        let token = iss.generate_token(&claims).unwrap();
+
+        let revealed = verify_with_jwks(&token, &set, &[]).unwrap();
+        assert_eq!(
+            revealed.get("subject").and_then(Value::as_str),
+            Some("carol")
+        );
+    }
+
+    #[test]
+    fn from_jwks_errors_when_kid_not_found() {
+        let set = JwkSet {
+            keys: vec![rsa_test_jwk("known-key")],
+        };
+
+        let err = Issuer::from_jwks(&set, "missing-key").unwrap_err();
+        assert!(matches!(err, JwtError::MalformedJwk(_)));
+    }
+
+    #[test]
+    fn verify_with_jwks_errors_when_token_kid_is_not_in_set() {
+        let iss = Issuer::from_jwk(rsa_test_jwk("issuer-key")).unwrap();
+        let claims = CustomClaims {
+            subject: "dave".into(),
+        };
+        let token = iss.generate_token(&claims).unwrap();
+
+        let other_set = JwkSet {
+            keys: vec![rsa_test_jwk("unrelated-key")],
+        };
+        let err = verify_with_jwks(&token, &other_set, &[]).unwrap_err();
+        assert!(matches!(err, JwtError::MalformedJwk(_)));
+    }
+
+    #[test]
+    fn alg_confusion_header_alg_mismatched_with_key_is_rejected() {
+        use p256::ecdsa::signature::RandomizedSigner;
+
+        // The victim's kid resolves to an RSA key, but a header claiming
+        // `alg: "ES256"` must not be allowed to pick an attacker-chosen
+        // verification path through that same JWK.
+        let jwk = rsa_test_jwk("victim-key");
+        let attacker_key = p256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+
+        let header = serde_json::json!({"alg": "ES256", "typ": "JWT", "kid": "victim-key"});
+        let payload = serde_json::json!({"subject": "mallory"});
+        let signing_input = format!(
+            "{}.{}",
+            b64(&serde_json::to_vec(&header).unwrap()),
+            b64(&serde_json::to_vec(&payload).unwrap())
+        );
+        let signature: p256::ecdsa::Signature =
+            attacker_key.sign_with_rng(&mut rand::thread_rng(), signing_input.as_bytes());
+
+        let token = Token {
+            jws: format!("{signing_input}.{}", b64(&signature.to_bytes())),
+            disclosures: Vec::new(),
+        };
+
+        let err = verify(&token, &jwk, &[]).unwrap_err();
+        assert!(matches!(err, JwtError::UnsupportedAlg(_)));
+    }
+}
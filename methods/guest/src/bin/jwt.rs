@@ -0,0 +1,43 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proves possession of a validly-signed JWT without revealing anything
+//! beyond the claims the prover chooses to commit.
+
+#![no_main]
+
+use std::io::Read;
+
+use jwt_core::{CustomClaims, JwkSet, Token};
+use risc0_zkvm::guest::env;
+
+risc0_zkvm::guest::entry_point!(main);
+
+/// The signing keys' public components, fixed inside the guest so a proof
+/// can only be produced for tokens issued by one of this example's keys.
+/// The token's header `kid` selects which one to verify against.
+const JWKS: &str = include_str!("../../jwks_pub.json");
+
+fn main() {
+    let mut input = Vec::<u8>::new();
+    env::stdin().read_to_end(&mut input).unwrap();
+    let token: Token = bincode::deserialize(&input).expect("failed to decode token");
+
+    let jwks: JwkSet = JWKS.parse().expect("failed to parse embedded JWK set");
+    let claims =
+        jwt_core::verify_with_jwks(&token, &jwks, &[]).expect("token verification failed");
+    let claims: CustomClaims = serde_json::from_value(claims.into()).expect("unexpected claim shape");
+
+    env::commit(&claims.subject);
+}
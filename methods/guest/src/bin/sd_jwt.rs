@@ -0,0 +1,41 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verifies an SD-JWT and commits only the claims the holder chose to
+//! disclose, proving "I hold a token asserting X" without leaking the rest
+//! of the token.
+
+#![no_main]
+
+use std::io::Read;
+
+use jwt_core::{JwkSet, SdJwtInput};
+use risc0_zkvm::guest::env;
+
+risc0_zkvm::guest::entry_point!(main);
+
+const JWKS: &str = include_str!("../../jwks_pub.json");
+
+fn main() {
+    let mut input_bytes = Vec::<u8>::new();
+    env::stdin().read_to_end(&mut input_bytes).unwrap();
+    let input: SdJwtInput = bincode::deserialize(&input_bytes).expect("failed to decode SD-JWT input");
+
+    let jwks: JwkSet = JWKS.parse().expect("failed to parse embedded JWK set");
+    let disclose: Vec<&str> = input.disclose.iter().map(String::as_str).collect();
+    let claims = jwt_core::verify_with_jwks(&input.token, &jwks, &disclose)
+        .expect("SD-JWT verification failed");
+
+    env::commit(&serde_json::to_string(&claims).expect("claims always serialize"));
+}